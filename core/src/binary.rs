@@ -0,0 +1,24 @@
+//! Lossless embedding of binary file contents as base64 or hex text, so
+//! binary files can round-trip through the combined output instead of
+//! being dropped or mangled through lossy UTF-8 conversion.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+/// Matches the MIME base64 line length so encoded blocks stay readable.
+const WRAP_COLUMNS: usize = 76;
+
+pub(crate) fn encode_base64(buf: &[u8]) -> String {
+    wrap(&STANDARD.encode(buf))
+}
+
+pub(crate) fn encode_hex(buf: &[u8]) -> String {
+    wrap(&hex::encode(buf))
+}
+
+fn wrap(s: &str) -> String {
+    s.as_bytes()
+        .chunks(WRAP_COLUMNS)
+        .map(|chunk| std::str::from_utf8(chunk).expect("base64/hex alphabets are ASCII"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
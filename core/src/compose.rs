@@ -0,0 +1,72 @@
+//! Recursive composition of the stdin `Input` via its `include` field.
+//!
+//! `include` names other manifest JSON files (same shape as `Input`) whose
+//! `paths`/`exclude`/`include_glob` are folded into the current one before
+//! expansion, so a shared base manifest can be layered with project-specific
+//! ones instead of duplicating path lists.
+
+use crate::Input;
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Resolves `input`'s `include` chain in place, relative to `base_dir`
+/// (the directory of the manifest `input` itself came from, or the CWD
+/// for the top-level stdin JSON).
+pub(crate) fn resolve_includes(input: &mut Input, base_dir: &Path) -> Result<()> {
+    let mut visited = HashSet::new();
+    merge_includes(input, base_dir, &mut visited)
+}
+
+fn merge_includes(input: &mut Input, base_dir: &Path, visited: &mut HashSet<PathBuf>) -> Result<()> {
+    let Some(manifests) = input.include.take() else {
+        return Ok(());
+    };
+
+    for rel in manifests {
+        let manifest_path = base_dir.join(&rel);
+        let canonical = fs::canonicalize(&manifest_path)
+            .with_context(|| format!("Cannot resolve manifest include {}", manifest_path.display()))?;
+
+        // Stack semantics: a manifest is only a cycle if it's an ancestor of
+        // itself in the current include chain, not merely reachable from two
+        // sibling branches (the common "shared base manifest" layering case).
+        if !visited.insert(canonical.clone()) {
+            bail!("Cycle detected in manifest includes at {}", canonical.display());
+        }
+
+        let text = fs::read_to_string(&canonical)
+            .with_context(|| format!("Failed to read manifest {}", canonical.display()))?;
+        let mut nested: Input = serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse manifest {}", canonical.display()))?;
+
+        let nested_dir = canonical.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+        merge_includes(&mut nested, &nested_dir, visited)?;
+        visited.remove(&canonical);
+
+        input.paths.extend(
+            nested.paths.into_iter().map(|p| resolve_against(&nested_dir, &p)),
+        );
+        if let Some(exclude) = nested.exclude {
+            input.exclude.get_or_insert_with(Vec::new).extend(exclude);
+        }
+        if let Some(include_glob) = nested.include_glob {
+            input.include_glob.get_or_insert_with(Vec::new).extend(include_glob);
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves a nested manifest's `paths` entry against that manifest's own
+/// directory, the same way its `include` pointers are resolved, so a base
+/// manifest's paths stay correct regardless of where it's included from.
+fn resolve_against(base_dir: &Path, path: &str) -> String {
+    let p = Path::new(path);
+    if p.is_absolute() {
+        path.to_string()
+    } else {
+        base_dir.join(p).to_string_lossy().into_owned()
+    }
+}
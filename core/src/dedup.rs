@@ -0,0 +1,97 @@
+//! Content-based deduplication.
+//!
+//! Identical files are detected with a two-phase hash so we only pay for
+//! hashing proportional to genuine collisions instead of total bytes:
+//! first files are bucketed by length (different lengths can never be
+//! equal), then within a length bucket a partial hash over just the first
+//! 4096 bytes narrows candidates further, and only a partial-hash collision
+//! triggers a full-file hash to confirm equality.
+
+use anyhow::{Context, Result};
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hasher;
+use std::path::PathBuf;
+
+const PARTIAL_HASH_BYTES: usize = 4096;
+
+/// For each file, `None` if it's the canonical (first) copy of its content,
+/// or `Some(index)` of the canonical file if it's a byte-identical duplicate.
+pub(crate) fn find_duplicates(files: &[PathBuf]) -> Result<Vec<Option<usize>>> {
+    let mut dup_of = vec![None; files.len()];
+
+    let mut by_len: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, f) in files.iter().enumerate() {
+        let len = fs::metadata(f)
+            .with_context(|| format!("Failed to get metadata for {}", f.display()))?
+            .len();
+        by_len.entry(len).or_default().push(i);
+    }
+
+    for indices in by_len.into_values() {
+        if indices.len() < 2 {
+            continue;
+        }
+
+        let mut by_partial: HashMap<u128, Vec<usize>> = HashMap::new();
+        for i in indices {
+            let hash = hash_prefix(&files[i], PARTIAL_HASH_BYTES)?;
+            by_partial.entry(hash).or_default().push(i);
+        }
+
+        for candidates in by_partial.into_values() {
+            if candidates.len() < 2 {
+                continue;
+            }
+
+            let mut by_full: HashMap<u128, Vec<usize>> = HashMap::new();
+            for i in candidates {
+                let hash = hash_file(&files[i])?;
+                by_full.entry(hash).or_default().push(i);
+            }
+
+            for mut group in by_full.into_values() {
+                if group.len() < 2 {
+                    continue;
+                }
+                group.sort_unstable();
+                let canonical = group[0];
+                for &dup in &group[1..] {
+                    dup_of[dup] = Some(canonical);
+                }
+            }
+        }
+    }
+
+    Ok(dup_of)
+}
+
+fn hash_prefix(path: &std::path::Path, max_bytes: usize) -> Result<u128> {
+    use std::io::Read;
+    let mut f = fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut buf = vec![0u8; max_bytes];
+    let mut total = 0;
+    while total < buf.len() {
+        let n = f.read(&mut buf[total..])
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        total += n;
+    }
+    Ok(hash_bytes(&buf[..total]))
+}
+
+fn hash_file(path: &std::path::Path) -> Result<u128> {
+    let buf = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(hash_bytes(&buf))
+}
+
+fn hash_bytes(buf: &[u8]) -> u128 {
+    let mut hasher = SipHasher13::new();
+    hasher.write(buf);
+    let h = hasher.finish128();
+    ((h.h1 as u128) << 64) | h.h2 as u128
+}
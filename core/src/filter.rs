@@ -0,0 +1,244 @@
+//! Gitignore-aware and glob-based filtering used by `expand()`.
+//!
+//! Four independent mechanisms feed into whether a walked path is kept:
+//!   - `.gitignore` files, applied hierarchically (closest file wins, `!` re-includes)
+//!   - explicit `--exclude` globs, matched against the path relative to `workspace_root`
+//!   - explicit `--include` globs, which act as an allowlist evaluated *after* excludes
+//!   - explicit `--ext` extensions, which act as an allowlist on the file's extension alone
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single parsed line from a `.gitignore` file.
+struct GitignoreEntry {
+    matcher: globset::GlobMatcher,
+    negate: bool,
+    dir_only: bool,
+}
+
+/// The set of `.gitignore` entries that apply underneath `base_dir`.
+pub(crate) struct GitignoreLayer {
+    base_dir: PathBuf,
+    entries: Vec<GitignoreEntry>,
+}
+
+/// Compiled `--exclude`/`--include`/`--ext`/`.gitignore` state for one `expand()` call.
+pub(crate) struct FilterOptions {
+    pub(crate) respect_gitignore: bool,
+    excludes: GlobSet,
+    includes: Option<GlobSet>,
+    extensions: Option<HashSet<String>>,
+    workspace_root: Option<PathBuf>,
+}
+
+impl FilterOptions {
+    pub(crate) fn new(
+        respect_gitignore: bool,
+        excludes: &[String],
+        includes: &[String],
+        extensions: &[String],
+        workspace_root: Option<&Path>,
+    ) -> Result<Self> {
+        Ok(Self {
+            respect_gitignore,
+            excludes: compile_globs(excludes).context("Invalid --exclude glob")?,
+            includes: if includes.is_empty() {
+                None
+            } else {
+                Some(compile_globs(includes).context("Invalid --include glob")?)
+            },
+            extensions: if extensions.is_empty() {
+                None
+            } else {
+                Some(extensions.iter().map(|e| e.trim_start_matches('.').to_ascii_lowercase()).collect())
+            },
+            workspace_root: workspace_root.map(|p| p.to_path_buf()),
+        })
+    }
+
+    /// Whether `path` should be dropped by the `--exclude`/`--include` globs.
+    /// Matched against the path relative to `workspace_root` when one is set,
+    /// otherwise against the path as given.
+    fn excluded_by_globs(&self, path: &Path) -> bool {
+        let rel = self
+            .workspace_root
+            .as_deref()
+            .and_then(|w| pathdiff::diff_paths(path, w))
+            .unwrap_or_else(|| path.to_path_buf());
+
+        if self.excludes.is_match(&rel) {
+            return true;
+        }
+        if let Some(includes) = &self.includes {
+            return !includes.is_match(&rel);
+        }
+        false
+    }
+
+    /// Whether `path` survives the `--exclude`/`--include` globs.
+    pub(crate) fn keep(&self, path: &Path) -> bool {
+        !self.excluded_by_globs(path)
+    }
+
+    /// Whether `path`'s extension is in the `--ext` allowlist, matched
+    /// case-insensitively. Always true when no `--ext` was given.
+    pub(crate) fn matches_ext(&self, path: &Path) -> bool {
+        match &self.extensions {
+            None => true,
+            Some(exts) => path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| exts.contains(&e.to_ascii_lowercase()))
+                .unwrap_or(false),
+        }
+    }
+}
+
+fn compile_globs(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for p in patterns {
+        builder.add(Glob::new(p).with_context(|| format!("bad glob pattern: {p}"))?);
+    }
+    builder.build().context("Failed to build glob set")
+}
+
+/// Collects the `.gitignore` layers that apply to `dir` from its own ancestors,
+/// stopping at `boundary` (inclusive) if given, otherwise at the filesystem root.
+pub(crate) fn ancestor_layers(dir: &Path, boundary: Option<&Path>) -> Vec<GitignoreLayer> {
+    let mut layers = Vec::new();
+    let mut cur = dir.parent();
+    while let Some(d) = cur {
+        if let Some(layer) = load_layer(d) {
+            layers.push(layer);
+        }
+        if Some(d) == boundary {
+            break;
+        }
+        cur = d.parent();
+    }
+    layers.reverse(); // outermost ancestor first, so closer directories override later
+    layers
+}
+
+fn load_layer(dir: &Path) -> Option<GitignoreLayer> {
+    let path = dir.join(".gitignore");
+    let text = fs::read_to_string(&path).ok()?;
+    let entries = text
+        .lines()
+        .filter_map(parse_gitignore_line)
+        .collect();
+    Some(GitignoreLayer {
+        base_dir: dir.to_path_buf(),
+        entries,
+    })
+}
+
+fn parse_gitignore_line(line: &str) -> Option<GitignoreEntry> {
+    let trimmed = line.trim_end();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let mut pat = trimmed;
+    let negate = if let Some(rest) = pat.strip_prefix('!') {
+        pat = rest;
+        true
+    } else {
+        false
+    };
+    let anchored = pat.starts_with('/');
+    let mut pat = pat.trim_start_matches('/').to_string();
+    let dir_only = pat.ends_with('/');
+    if dir_only {
+        pat.pop();
+    }
+    if pat.is_empty() {
+        return None;
+    }
+    // Unanchored patterns (no '/' in the body) match at any depth, same as git.
+    let glob_str = if anchored || pat.contains('/') {
+        pat
+    } else {
+        format!("**/{pat}")
+    };
+    let glob = Glob::new(&glob_str).ok()?;
+    Some(GitignoreEntry {
+        matcher: glob.compile_matcher(),
+        negate,
+        dir_only,
+    })
+}
+
+/// Stack of `.gitignore` layers maintained while walking a single root directory.
+pub(crate) struct GitignoreStack {
+    /// Layers from the root's ancestors; they apply to every entry of the walk.
+    seed: Vec<GitignoreLayer>,
+    layers: Vec<(usize, GitignoreLayer)>,
+}
+
+impl GitignoreStack {
+    pub(crate) fn new(seed: Vec<GitignoreLayer>) -> Self {
+        Self {
+            seed,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Drop layers that no longer apply now that we've moved to `depth`.
+    pub(crate) fn pop_to(&mut self, depth: usize) {
+        self.layers.retain(|(d, _)| *d < depth);
+    }
+
+    /// Register `dir`'s own `.gitignore` (if any) as scoping everything below it.
+    pub(crate) fn push_dir(&mut self, depth: usize, dir: &Path) {
+        if let Some(layer) = load_layer(dir) {
+            self.layers.push((depth, layer));
+        }
+    }
+
+    /// Whether `path` (a directory if `is_dir`) is ignored by the ancestor
+    /// layers and the layers currently on the stack.
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for layer in self.seed.iter().chain(self.layers.iter().map(|(_, l)| l)) {
+            let Ok(rel) = path.strip_prefix(&layer.base_dir) else {
+                continue;
+            };
+            for entry in &layer.entries {
+                if entry_matches(entry, rel, is_dir) {
+                    ignored = !entry.negate;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+fn entry_matches(entry: &GitignoreEntry, rel: &Path, is_dir: bool) -> bool {
+    let comps: Vec<_> = rel.components().collect();
+    if comps.is_empty() {
+        return false;
+    }
+    let mut cur = PathBuf::new();
+    for (i, c) in comps.iter().enumerate() {
+        cur.push(c);
+        let is_last = i == comps.len() - 1;
+        let comp_is_dir = !is_last || is_dir;
+        if entry.dir_only && !comp_is_dir {
+            continue;
+        }
+        if entry.matcher.is_match(&cur) {
+            return true;
+        }
+    }
+    false
+}
+
+pub(crate) fn seed_layers(filters: &FilterOptions, root: &Path) -> Vec<GitignoreLayer> {
+    if !filters.respect_gitignore {
+        return Vec::new();
+    }
+    ancestor_layers(root, filters.workspace_root.as_deref())
+}
@@ -6,6 +6,21 @@ use tempfile::Builder as TempBuilder;
 use walkdir::WalkDir;
 use atty::Stream;
 
+mod binary;
+mod compose;
+mod dedup;
+mod filter;
+mod manifest;
+use filter::FilterOptions;
+use manifest::{FileRecord, SkipReason};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum BinaryMode {
+    Skip,
+    Base64,
+    Hex,
+}
+
 #[derive(Parser, Debug)]
 // #[command(arg_required_else_help = true)]
 struct Args {
@@ -29,6 +44,10 @@ struct Args {
     #[arg(long)]
     skip_binary: bool,
 
+    /// How to handle binary-like files when --skip-binary is set: skip | base64 | hex
+    #[arg(long, value_enum, default_value_t = BinaryMode::Skip)]
+    binary_mode: BinaryMode,
+
     /// Optional RAM dir (Linux: /run/user/$UID or /dev/shm)
     #[arg(long)]
     ram_dir: Option<PathBuf>,
@@ -36,13 +55,54 @@ struct Args {
     /// Enable verbose debug output
     #[arg(long, short)]
     verbose: bool,
+
+    /// Walk upward from each root applying .gitignore files hierarchically
+    #[arg(long)]
+    respect_gitignore: bool,
+
+    /// Exclude paths matching this glob (relative to workspace_root), repeatable
+    #[arg(long = "exclude")]
+    exclude: Vec<String>,
+
+    /// Allowlist: if set, only paths matching one of these globs survive (applied after excludes)
+    #[arg(long = "include")]
+    include: Vec<String>,
+
+    /// Limit directory recursion depth (0 = only the named path itself, 1 = immediate children, ...)
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Follow symlinks while walking directories, descending into symlinked dirs
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Keep only files with this extension (without the leading dot), repeatable
+    #[arg(long = "ext")]
+    ext: Vec<String>,
+
+    /// Collapse byte-identical files, emitting content once and a reference for the rest
+    #[arg(long)]
+    dedup: bool,
+
+    /// Write a Make-syntax depfile listing every file that was combined
+    #[arg(long)]
+    depfile: Option<PathBuf>,
+
+    /// Write a JSON manifest of every file considered, with size and skip reason
+    #[arg(long)]
+    manifest_json: Option<PathBuf>,
 }
 
 #[derive(Deserialize)]
-struct Input {
-    paths: Vec<String>,
+pub(crate) struct Input {
+    pub(crate) paths: Vec<String>,
     #[serde(alias = "workspaceRoot")] // backward compatibility
-    workspace_root: Option<String>,
+    pub(crate) workspace_root: Option<String>,
+    pub(crate) exclude: Option<Vec<String>>,
+    /// Allowlist glob patterns, applied after `exclude` (see `--include`)
+    pub(crate) include_glob: Option<Vec<String>>,
+    /// Other manifest files (same shape as this struct) to merge in before expansion
+    pub(crate) include: Option<Vec<String>>,
 }
 
 fn ensure_stdin_is_piped() -> Result<()> {
@@ -73,31 +133,123 @@ fn pick_tmp_dir(ram_dir: &Option<PathBuf>) -> PathBuf {
     std::env::temp_dir()
 }
 
-fn expand(paths: &[String], verbose: bool) -> Result<Vec<PathBuf>> {
+/// How a walked entry was classified, so symlinks-to-files, regular files
+/// and directories are handled explicitly instead of falling through a
+/// single is_file()/is_dir() branch.
+enum EntryKind {
+    Directory,
+    RegularFile,
+    SymlinkToFile,
+    /// Sockets, FIFOs, device nodes, etc. — never embedded.
+    Other,
+}
+
+fn classify_entry(entry: &walkdir::DirEntry) -> EntryKind {
+    let ft = entry.file_type();
+    if ft.is_dir() {
+        return EntryKind::Directory;
+    }
+    if ft.is_symlink() {
+        // `ft` here is the symlink's own metadata (walkdir only reports the
+        // target's type when `follow_links` is on, in which case is_symlink()
+        // is already false). Resolve it to see what it actually points at,
+        // rather than assuming every symlink is a file.
+        return match fs::metadata(entry.path()) {
+            Ok(target) if target.is_file() => EntryKind::SymlinkToFile,
+            Ok(target) if target.is_dir() => EntryKind::Directory,
+            _ => EntryKind::Other,
+        };
+    }
+    if ft.is_file() {
+        EntryKind::RegularFile
+    } else {
+        EntryKind::Other
+    }
+}
+
+/// Per-root bookkeeping surfaced in verbose output, so users can see why a
+/// directory expansion did or didn't include what they expected.
+#[derive(Default)]
+struct WalkStats {
+    files_found: usize,
+    dirs_descended: usize,
+    filtered_by_ext: usize,
+    dirs_truncated_by_depth: usize,
+}
+
+fn expand(
+    paths: &[String],
+    verbose: bool,
+    filters: &FilterOptions,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+) -> Result<Vec<PathBuf>> {
     let mut out = Vec::new();
     let mut errors = Vec::new();
-    
+
     for p in paths {
         let p = PathBuf::from(p);
         debug_print(verbose, &format!("Processing path: {}", p.display()));
-        
+
         match fs::metadata(&p) {
             Ok(md) => {
-                if md.is_file() { 
-                    debug_print(verbose, &format!("  -> Found file: {}", p.display()));
-                    out.push(p); 
+                if md.is_file() {
+                    if filters.keep(&p) && filters.matches_ext(&p) {
+                        debug_print(verbose, &format!("  -> Found file: {}", p.display()));
+                        out.push(p);
+                    } else {
+                        debug_print(verbose, &format!("  -> Excluded: {}", p.display()));
+                    }
                 } else if md.is_dir() {
                     debug_print(verbose, &format!("  -> Expanding directory: {}", p.display()));
-                    let mut dir_files = 0;
-                    for e in WalkDir::new(&p).into_iter() {
+                    let mut stats = WalkStats::default();
+                    let mut stack = filter::GitignoreStack::new(filter::seed_layers(filters, &p));
+                    let mut builder = WalkDir::new(&p).follow_links(follow_symlinks);
+                    if let Some(depth) = max_depth {
+                        builder = builder.max_depth(depth);
+                    }
+                    let walker = builder.into_iter().filter_entry(|entry| {
+                        if !filters.respect_gitignore {
+                            return true;
+                        }
+                        stack.pop_to(entry.depth());
+                        let keep = !stack.is_ignored(entry.path(), entry.file_type().is_dir());
+                        if keep && entry.file_type().is_dir() {
+                            stack.push_dir(entry.depth(), entry.path());
+                        }
+                        keep
+                    });
+                    for e in walker {
                         match e {
-                            Ok(entry) => {
-                                if entry.file_type().is_file() { 
-                                    debug_print(verbose, &format!("    -> Found file in dir: {}", entry.path().display()));
-                                    out.push(entry.into_path()); 
-                                    dir_files += 1;
+                            Ok(entry) => match classify_entry(&entry) {
+                                EntryKind::Directory => {
+                                    // WalkDir's own max_depth stops yielding entries below
+                                    // this point, so there's nothing further to count; the
+                                    // boundary directory itself is the visible signal that
+                                    // its children were cut off.
+                                    if max_depth == Some(entry.depth()) {
+                                        stats.dirs_truncated_by_depth += 1;
+                                    } else if entry.depth() > 0 && entry.file_type().is_dir() {
+                                        // Symlinked dirs only report is_dir() when followed.
+                                        stats.dirs_descended += 1;
+                                    }
                                 }
-                            }
+                                EntryKind::RegularFile | EntryKind::SymlinkToFile => {
+                                    if !filters.keep(entry.path()) {
+                                        debug_print(verbose, &format!("    -> Excluded: {}", entry.path().display()));
+                                    } else if !filters.matches_ext(entry.path()) {
+                                        stats.filtered_by_ext += 1;
+                                        debug_print(verbose, &format!("    -> Filtered by --ext: {}", entry.path().display()));
+                                    } else {
+                                        debug_print(verbose, &format!("    -> Found file in dir: {}", entry.path().display()));
+                                        out.push(entry.into_path());
+                                        stats.files_found += 1;
+                                    }
+                                }
+                                EntryKind::Other => {
+                                    debug_print(verbose, &format!("    -> Skipped (not a file or dir): {}", entry.path().display()));
+                                }
+                            },
                             Err(err) => {
                                 let msg = format!("Failed to access {}: {}", p.display(), err);
                                 debug_print(verbose, &format!("  -> Error: {}", msg));
@@ -105,7 +257,13 @@ fn expand(paths: &[String], verbose: bool) -> Result<Vec<PathBuf>> {
                             }
                         }
                     }
-                    debug_print(verbose, &format!("  -> Found {} files in directory", dir_files));
+                    debug_print(
+                        verbose,
+                        &format!(
+                            "  -> {} files found, {} dirs descended, {} files filtered by --ext, {} dirs truncated by --max-depth",
+                            stats.files_found, stats.dirs_descended, stats.filtered_by_ext, stats.dirs_truncated_by_depth,
+                        ),
+                    );
                 } else {
                     let msg = format!("{} is neither file nor directory", p.display());
                     debug_print(verbose, &format!("  -> Error: {}", msg));
@@ -119,15 +277,15 @@ fn expand(paths: &[String], verbose: bool) -> Result<Vec<PathBuf>> {
             }
         }
     }
-    
+
     if !errors.is_empty() && out.is_empty() {
         bail!("No accessible files found. Errors:\n{}", errors.join("\n"));
     }
-    
+
     if !errors.is_empty() {
         debug_print(verbose, &format!("Some errors occurred but {} files found:\n{}", out.len(), errors.join("\n")));
     }
-    
+
     Ok(out)
 }
 
@@ -162,40 +320,65 @@ fn main() -> Result<()> {
 
     debug_print(args.verbose, &format!("Input JSON: {}", s.trim()));
 
-    let input: Input = serde_json::from_str(&s)
+    let mut input: Input = serde_json::from_str(&s)
         .context("Failed to parse JSON input. Expected format: {\"paths\":[\"path1\",\"path2\"],\"workspace_root\":\"optional\"}")?;
-    
+
+    let cwd = std::env::current_dir().context("Failed to determine current directory")?;
+    compose::resolve_includes(&mut input, &cwd)
+        .context("Failed to resolve manifest includes")?;
+
     debug_print(args.verbose, &format!("Parsed input: paths={:?}, workspace_root={:?}", input.paths, input.workspace_root));
 
     if input.paths.is_empty() {
         bail!("No paths provided in input JSON");
     }
 
-    let files = expand(&input.paths, args.verbose)
+    let ws = input.workspace_root.as_deref();
+
+    let excludes: Vec<String> = args.exclude.iter().cloned()
+        .chain(input.exclude.clone().unwrap_or_default())
+        .collect();
+    let includes: Vec<String> = args.include.iter().cloned()
+        .chain(input.include_glob.clone().unwrap_or_default())
+        .collect();
+    let filters = FilterOptions::new(args.respect_gitignore, &excludes, &includes, &args.ext, ws.map(Path::new))
+        .context("Failed to compile --exclude/--include globs")?;
+
+    let files = expand(&input.paths, args.verbose, &filters, args.max_depth, args.follow_symlinks)
         .context("Failed to expand paths to files")?;
     debug_print(args.verbose, &format!("Expanded {} paths to {} files", input.paths.len(), files.len()));
-    
-    if files.is_empty() { 
-        bail!("No files found from provided paths: {:?}", input.paths); 
+
+    if files.is_empty() {
+        bail!("No files found from provided paths: {:?}", input.paths);
     }
 
-    let ws = input.workspace_root.as_deref();
     let sep = unescape(&args.separator);
     let max_bytes = args.max_kb * 1024;
 
+    let rels: Vec<PathBuf> = files.iter()
+        .map(|f| ws.map(|w| pathdiff::diff_paths(f, w).unwrap_or_else(|| f.clone()))
+                   .unwrap_or_else(|| f.clone()))
+        .collect();
+
+    let dup_of = if args.dedup {
+        debug_print(args.verbose, "Finding byte-identical duplicates...");
+        dedup::find_duplicates(&files).context("Failed to deduplicate files")?
+    } else {
+        vec![None; files.len()]
+    };
+
     let mut acc = String::new();
     let mut processed = 0;
     let mut skipped = 0;
-    
+    let mut records: Vec<FileRecord> = Vec::with_capacity(files.len());
+
     debug_print(args.verbose, "Starting file processing...");
-    
+
     for (i, f) in files.iter().enumerate() {
         debug_print(args.verbose, &format!("Processing file {} of {}: {}", i+1, files.len(), f.display()));
-        
+
         let base = f.file_name().and_then(|x| x.to_str()).unwrap_or("unknown");
-        let rel = ws.map(|w| pathdiff::diff_paths(f, w).unwrap_or(f.clone()))
-                    .unwrap_or_else(|| f.clone());
-        let rel_s = rel.to_string_lossy();
+        let rel_s = rels[i].to_string_lossy();
 
         let header = args.header_format
             .replace("${index}", &(i+1).to_string())
@@ -205,27 +388,60 @@ fn main() -> Result<()> {
         acc.push_str(&header);
         acc.push('\n');
 
-        let meta = fs::metadata(f)
-            .with_context(|| format!("Failed to get metadata for {}", f.display()))?;
-            
-        if meta.len() as usize > max_bytes {
-            debug_print(args.verbose, &format!("  -> Skipped: too large ({} bytes > {} bytes)", meta.len(), max_bytes));
+        let len = fs::metadata(f)
+            .with_context(|| format!("Failed to get metadata for {}", f.display()))?
+            .len();
+
+        if let Some(canonical) = dup_of[i] {
+            debug_print(args.verbose, &format!("  -> Duplicate of file {}", canonical + 1));
+            acc.push_str(&format!("<duplicate of file {}: {}>\n", canonical + 1, rels[canonical].to_string_lossy()));
+            skipped += 1;
+            records.push(FileRecord { index: i + 1, rel_path: rels[i].clone(), len, skip_reason: Some(SkipReason::Duplicate) });
+            if i + 1 != files.len() { acc.push_str(&sep); }
+            continue;
+        }
+
+        if len as usize > max_bytes {
+            debug_print(args.verbose, &format!("  -> Skipped: too large ({} bytes > {} bytes)", len, max_bytes));
             acc.push_str("<skipped: too large>\n");
             skipped += 1;
+            records.push(FileRecord { index: i + 1, rel_path: rels[i].clone(), len, skip_reason: Some(SkipReason::TooLarge) });
         } else {
             let buf = fs::read(f)
                 .with_context(|| format!("Failed to read file {}", f.display()))?;
-                
+
             if args.skip_binary && is_binary(&buf) {
-                debug_print(args.verbose, "  -> Skipped: binary file detected");
-                acc.push_str("<skipped: binary>\n");
-                skipped += 1;
+                match args.binary_mode {
+                    BinaryMode::Skip => {
+                        debug_print(args.verbose, "  -> Skipped: binary file detected");
+                        acc.push_str("<skipped: binary>\n");
+                        skipped += 1;
+                        records.push(FileRecord { index: i + 1, rel_path: rels[i].clone(), len, skip_reason: Some(SkipReason::Binary) });
+                    }
+                    BinaryMode::Base64 => {
+                        debug_print(args.verbose, &format!("  -> Embedded as base64: {} bytes", buf.len()));
+                        acc.push_str(&format!("<binary base64 {} {} bytes>\n", base, buf.len()));
+                        acc.push_str(&binary::encode_base64(&buf));
+                        acc.push('\n');
+                        processed += 1;
+                        records.push(FileRecord { index: i + 1, rel_path: rels[i].clone(), len, skip_reason: None });
+                    }
+                    BinaryMode::Hex => {
+                        debug_print(args.verbose, &format!("  -> Embedded as hex: {} bytes", buf.len()));
+                        acc.push_str(&format!("<binary hex {} {} bytes>\n", base, buf.len()));
+                        acc.push_str(&binary::encode_hex(&buf));
+                        acc.push('\n');
+                        processed += 1;
+                        records.push(FileRecord { index: i + 1, rel_path: rels[i].clone(), len, skip_reason: None });
+                    }
+                }
             } else {
                 debug_print(args.verbose, &format!("  -> Added: {} bytes", buf.len()));
                 let text = String::from_utf8_lossy(&buf);
                 acc.push_str(text.trim_end());
                 acc.push('\n');
                 processed += 1;
+                records.push(FileRecord { index: i + 1, rel_path: rels[i].clone(), len, skip_reason: None });
             }
         }
         if i + 1 != files.len() { acc.push_str(&sep); }
@@ -233,33 +449,47 @@ fn main() -> Result<()> {
     
     debug_print(args.verbose, &format!("File processing complete: {} processed, {} skipped", processed, skipped));
 
-    match args.mode.as_str() {
-        "clipboard" => { 
+    let target = match args.mode.as_str() {
+        "clipboard" => {
             debug_print(args.verbose, &format!("Output mode: clipboard, {} chars", acc.len()));
-            print!("{acc}"); 
+            print!("{acc}");
+            "-".to_string()
         }
         "temp" => {
             let dir = pick_tmp_dir(&args.ram_dir);
             debug_print(args.verbose, &format!("Output mode: temp file in {}", dir.display()));
-            
+
             let file = TempBuilder::new()
                 .prefix("combined-")
                 .suffix(".txt")
                 .tempfile_in(&dir)
                 .with_context(|| format!("Failed to create temp file in {}", dir.display()))?;
-                
+
             let path = file.into_temp_path();       // scheduled for deletion on drop
             let final_path = path.keep()
                 .context("Failed to keep temp file")?;          // we keep it for user
-                
+
             debug_print(args.verbose, &format!("Writing {} chars to {}", acc.len(), final_path.display()));
-            
+
             fs::write(&final_path, &acc)
                 .with_context(|| format!("Failed to write content to {}", final_path.display()))?;
-                
+
             println!("{}", final_path.to_string_lossy());
+            final_path.to_string_lossy().into_owned()
         }
         _ => bail!("Unknown mode '{}'. Use 'clipboard' or 'temp'", args.mode),
+    };
+
+    if let Some(depfile) = &args.depfile {
+        debug_print(args.verbose, &format!("Writing depfile to {}", depfile.display()));
+        manifest::write_depfile(depfile, &target, &records)
+            .context("Failed to write depfile")?;
     }
+    if let Some(manifest_json) = &args.manifest_json {
+        debug_print(args.verbose, &format!("Writing manifest JSON to {}", manifest_json.display()));
+        manifest::write_manifest_json(manifest_json, &records)
+            .context("Failed to write manifest JSON")?;
+    }
+
     Ok(())
 }
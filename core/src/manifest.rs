@@ -0,0 +1,72 @@
+//! Accounting of what actually went into the combined output, and writers
+//! for the `--depfile`/`--manifest-json` outputs build tools consume to
+//! detect staleness.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Why a file's content was left out of the combined output.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum SkipReason {
+    TooLarge,
+    Binary,
+    Duplicate,
+}
+
+impl SkipReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::TooLarge => "too-large",
+            SkipReason::Binary => "binary",
+            SkipReason::Duplicate => "duplicate",
+        }
+    }
+}
+
+/// One file that was considered for inclusion, post-expansion/post-ignore.
+pub(crate) struct FileRecord {
+    pub(crate) index: usize,
+    pub(crate) rel_path: PathBuf,
+    pub(crate) len: u64,
+    pub(crate) skip_reason: Option<SkipReason>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    index: usize,
+    path: String,
+    bytes: u64,
+    skip_reason: Option<String>,
+}
+
+pub(crate) fn write_manifest_json(path: &Path, records: &[FileRecord]) -> Result<()> {
+    let entries: Vec<ManifestEntry> = records
+        .iter()
+        .map(|r| ManifestEntry {
+            index: r.index,
+            path: r.rel_path.to_string_lossy().into_owned(),
+            bytes: r.len,
+            skip_reason: r.skip_reason.map(|s| s.as_str().to_string()),
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&entries).context("Failed to serialize manifest")?;
+    fs::write(path, json).with_context(|| format!("Failed to write manifest to {}", path.display()))
+}
+
+/// Writes a Make-syntax depfile: `target: input1 input2 ...`, with spaces
+/// in input paths escaped so `make`/`ninja` parse them as one dependency each.
+pub(crate) fn write_depfile(path: &Path, target: &str, records: &[FileRecord]) -> Result<()> {
+    let mut line = format!("{}:", escape_make(target));
+    for record in records {
+        line.push(' ');
+        line.push_str(&escape_make(&record.rel_path.to_string_lossy()));
+    }
+    line.push('\n');
+    fs::write(path, line).with_context(|| format!("Failed to write depfile to {}", path.display()))
+}
+
+fn escape_make(s: &str) -> String {
+    s.replace(' ', "\\ ")
+}
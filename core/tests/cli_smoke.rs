@@ -263,3 +263,280 @@ fn clipboard_empty_files_handling() {
     let empty_section = out.split("File: normal.txt").next().unwrap();
     assert!(empty_section.contains("File: empty.txt"));
 }
+
+#[test]
+fn respect_gitignore_excludes_ignored_files() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".gitignore"), "ignored.txt\n").unwrap();
+    fs::write(dir.path().join("ignored.txt"), "secret").unwrap();
+    fs::write(dir.path().join("kept.txt"), "keep me").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, out, _err) = run_cli(&json, &["--mode", "clipboard", "--respect-gitignore"]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("keep me"));
+    assert!(!out.contains("secret"));
+}
+
+#[test]
+fn exclude_glob_drops_matching_files() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "a content").unwrap();
+    fs::write(dir.path().join("b.log"), "b content").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, out, _err) = run_cli(&json, &["--mode", "clipboard", "--exclude", "*.log"]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("a content"));
+    assert!(!out.contains("b content"));
+}
+
+#[test]
+fn include_glob_acts_as_allowlist_after_exclude() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("keep.rs"), "rust content").unwrap();
+    fs::write(dir.path().join("drop.txt"), "text content").unwrap();
+    fs::write(dir.path().join("skip.rs"), "excluded rust").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, out, _err) = run_cli(&json, &["--mode", "clipboard", "--include", "*.rs", "--exclude", "*skip.rs"]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("rust content"));
+    assert!(!out.contains("text content"));
+    assert!(!out.contains("excluded rust"));
+}
+
+#[test]
+fn dedup_collapses_byte_identical_files() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "same bytes").unwrap();
+    fs::write(dir.path().join("b.txt"), "same bytes").unwrap();
+    fs::write(dir.path().join("c.txt"), "different").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, out, _err) = run_cli(&json, &["--mode", "clipboard", "--dedup"]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("<duplicate of file "), "out: {}", out);
+    assert_eq!(out.matches("same bytes").count(), 1);
+    assert!(out.contains("different"));
+}
+
+#[test]
+fn dedup_keeps_distinct_content_separate() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "alpha").unwrap();
+    fs::write(dir.path().join("b.txt"), "beta").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, out, _err) = run_cli(&json, &["--mode", "clipboard", "--dedup"]);
+
+    assert_eq!(code, 0);
+    assert!(!out.contains("<duplicate of file"));
+    assert!(out.contains("alpha"));
+    assert!(out.contains("beta"));
+}
+
+#[test]
+fn depfile_and_manifest_json_agree_on_relative_paths() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "content").unwrap();
+    let depfile = dir.path().join("out.d");
+    let manifest = dir.path().join("out.json");
+
+    let json = format!(
+        r#"{{"paths":["{}"],"workspace_root":"{}"}}"#,
+        dir.path().display(), dir.path().display()
+    );
+    let (code, _out, _err) = run_cli(&json, &[
+        "--mode", "clipboard",
+        "--depfile", &depfile.to_string_lossy(),
+        "--manifest-json", &manifest.to_string_lossy(),
+    ]);
+
+    assert_eq!(code, 0);
+    let dep_contents = fs::read_to_string(&depfile).unwrap();
+    let manifest_contents = fs::read_to_string(&manifest).unwrap();
+
+    assert!(dep_contents.contains("a.txt"), "depfile: {}", dep_contents);
+    assert!(!dep_contents.contains(&dir.path().to_string_lossy().into_owned()), "depfile should list relative paths, not absolute ones: {}", dep_contents);
+    assert!(manifest_contents.contains("\"a.txt\""), "manifest: {}", manifest_contents);
+}
+
+#[test]
+fn manifest_json_records_skip_reason() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("small.txt"), "ok").unwrap();
+    fs::write(dir.path().join("big.txt"), "x".repeat(2000)).unwrap();
+    let manifest = dir.path().join("out.json");
+
+    let json = format!(
+        r#"{{"paths":["{}"],"workspace_root":"{}"}}"#,
+        dir.path().display(), dir.path().display()
+    );
+    let (code, _out, _err) = run_cli(&json, &[
+        "--mode", "clipboard",
+        "--max-kb", "1",
+        "--manifest-json", &manifest.to_string_lossy(),
+    ]);
+
+    assert_eq!(code, 0);
+    let manifest_contents = fs::read_to_string(&manifest).unwrap();
+    assert!(manifest_contents.contains("\"too-large\""), "manifest: {}", manifest_contents);
+}
+
+#[test]
+fn manifest_include_resolves_nested_paths_against_its_own_directory() {
+    let dir = tempdir().unwrap();
+    let libs = dir.path().join("libs");
+    fs::create_dir_all(&libs).unwrap();
+    fs::write(libs.join("lib.txt"), "library content").unwrap();
+    let base_manifest = libs.join("base.json");
+    fs::write(&base_manifest, r#"{"paths":["lib.txt"]}"#).unwrap();
+
+    let json = format!(r#"{{"paths":[],"include":["{}"]}}"#, base_manifest.display());
+    let (code, out, err) = run_cli(&json, &["--mode", "clipboard"]);
+
+    assert_eq!(code, 0, "stderr: {}", err);
+    assert!(out.contains("library content"));
+}
+
+#[test]
+fn manifest_include_diamond_does_not_false_positive_cycle() {
+    let dir = tempdir().unwrap();
+    let shared = dir.path().join("shared.json");
+    fs::write(&shared, r#"{"paths":["shared.txt"]}"#).unwrap();
+    fs::write(dir.path().join("shared.txt"), "shared content").unwrap();
+
+    let a = dir.path().join("a.json");
+    fs::write(&a, format!(r#"{{"paths":["a.txt"],"include":["{}"]}}"#, shared.display())).unwrap();
+    fs::write(dir.path().join("a.txt"), "a content").unwrap();
+
+    let b = dir.path().join("b.json");
+    fs::write(&b, format!(r#"{{"paths":["b.txt"],"include":["{}"]}}"#, shared.display())).unwrap();
+    fs::write(dir.path().join("b.txt"), "b content").unwrap();
+
+    let json = format!(r#"{{"paths":[],"include":["{}","{}"]}}"#, a.display(), b.display());
+    let (code, out, err) = run_cli(&json, &["--mode", "clipboard"]);
+
+    assert_eq!(code, 0, "stderr: {}", err);
+    assert!(!err.contains("Cycle detected"));
+    assert!(out.contains("a content"));
+    assert!(out.contains("b content"));
+    assert!(out.contains("shared content"));
+}
+
+#[test]
+fn manifest_include_true_cycle_is_detected() {
+    let dir = tempdir().unwrap();
+    let a = dir.path().join("a.json");
+    let b = dir.path().join("b.json");
+    fs::write(&a, format!(r#"{{"paths":[],"include":["{}"]}}"#, b.display())).unwrap();
+    fs::write(&b, format!(r#"{{"paths":[],"include":["{}"]}}"#, a.display())).unwrap();
+
+    let json = format!(r#"{{"paths":[],"include":["{}"]}}"#, a.display());
+    let (code, _out, err) = run_cli(&json, &["--mode", "clipboard"]);
+
+    assert_ne!(code, 0);
+    assert!(err.contains("Cycle detected"));
+}
+
+#[test]
+fn binary_mode_base64_round_trips_binary_content() {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    let dir = tempdir().unwrap();
+    let binary_bytes = vec![0u8, 1, 2, 3, 255, 254, 0, 128];
+    fs::write(dir.path().join("binary.bin"), &binary_bytes).unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().join("binary.bin").display());
+    let (code, out, _err) = run_cli(&json, &[
+        "--mode", "clipboard",
+        "--skip-binary",
+        "--binary-mode", "base64",
+    ]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("<binary base64"));
+    let encoded: String = out
+        .lines()
+        .skip_while(|l| !l.starts_with("<binary base64"))
+        .skip(1)
+        .take_while(|l| !l.is_empty())
+        .collect();
+    let decoded = STANDARD.decode(&encoded).unwrap();
+    assert_eq!(decoded, binary_bytes);
+}
+
+#[test]
+fn binary_mode_hex_round_trips_binary_content() {
+    let dir = tempdir().unwrap();
+    let binary_bytes = vec![0u8, 1, 2, 3, 255, 254, 0, 128];
+    fs::write(dir.path().join("binary.bin"), &binary_bytes).unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().join("binary.bin").display());
+    let (code, out, _err) = run_cli(&json, &[
+        "--mode", "clipboard",
+        "--skip-binary",
+        "--binary-mode", "hex",
+    ]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("<binary hex"));
+    let encoded: String = out
+        .lines()
+        .skip_while(|l| !l.starts_with("<binary hex"))
+        .skip(1)
+        .take_while(|l| !l.is_empty())
+        .collect();
+    let decoded = hex::decode(&encoded).unwrap();
+    assert_eq!(decoded, binary_bytes);
+}
+
+#[test]
+fn max_depth_zero_only_finds_the_named_path_itself() {
+    // Depth 0 is just the directory entry itself (never a file), so a
+    // directory with only nested files yields nothing and the CLI errors
+    // out the same way it would for any other empty expansion.
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("root.txt"), "root content").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, _out, err) = run_cli(&json, &["--mode", "clipboard", "--max-depth", "0"]);
+
+    assert_ne!(code, 0);
+    assert!(err.contains("No files found"), "stderr: {}", err);
+}
+
+#[test]
+fn max_depth_one_allows_immediate_children_but_not_grandchildren() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("root.txt"), "root content").unwrap();
+    let nested = dir.path().join("nested");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(nested.join("deep.txt"), "deep content").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, out, _err) = run_cli(&json, &["--mode", "clipboard", "--max-depth", "1"]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("root content"));
+    assert!(!out.contains("deep content"));
+}
+
+#[test]
+fn ext_filter_keeps_only_matching_extensions() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("keep.rs"), "rust content").unwrap();
+    fs::write(dir.path().join("drop.txt"), "text content").unwrap();
+
+    let json = format!(r#"{{"paths":["{}"]}}"#, dir.path().display());
+    let (code, out, _err) = run_cli(&json, &["--mode", "clipboard", "--ext", "rs"]);
+
+    assert_eq!(code, 0);
+    assert!(out.contains("rust content"));
+    assert!(!out.contains("text content"));
+}